@@ -0,0 +1,535 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use zed_extension_api::{self as zed, Worktree};
+
+/// Name of the `[language_servers.*]` entry in `extension.toml`, used to
+/// read `sqls`'s own settings block (see [`resolve_sqls_connections`]).
+const SQLS_LANGUAGE_SERVER_ID: &str = "sqls";
+
+/// The `connections` shape shared by the context server's
+/// `context_servers.database-operations-mcp.settings` block and `sqls`'s
+/// own `lsp.sqls.settings` block. Everything here is optional;
+/// a project with no `connections` table simply gets no extra environment.
+#[derive(Debug, Default, Deserialize)]
+struct DatabaseExtensionSettings {
+    #[serde(default)]
+    connections: HashMap<String, ConnectionSettings>,
+    /// Which entry in `connections` to use when more than one is defined.
+    #[serde(default)]
+    active_connection: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConnectionSettings {
+    /// A full connection string, used as-is if present.
+    dsn: Option<String>,
+    host: Option<String>,
+    port: Option<u16>,
+    user: Option<String>,
+    password: Option<String>,
+    database: Option<String>,
+    /// Path to a `.env`-style file, relative to the worktree root, whose
+    /// `DATABASE_URL` is used as the DSN when no `dsn` is set (so secrets
+    /// don't have to live in settings.json). No other key in it is read.
+    env_file: Option<String>,
+}
+
+/// Builds the environment variables that should be passed to the spawned
+/// context server process: one `DATABASE_URL`-style entry per configured
+/// connection.
+///
+/// `context_server_command` only ever receives a `Project`, which exposes no
+/// way to get at a `Worktree`, so (unlike [`resolve_sqls_connections`]) a
+/// `dsn`/`host` containing a `${VAR}` placeholder or an `env_file` can't be
+/// expanded here; [`literal_dsn`] rejects rather than silently passing
+/// through (or silently dropping) a field that needs one.
+///
+/// Every configured connection gets a `DATABASE_URL_<ALIAS>` so the server
+/// can target any of them; a plain `DATABASE_URL` is added for the only
+/// connection, or for `active_connection` when several are configured.
+pub fn resolve_connection_env(
+    context_server_id: &zed::ContextServerId,
+    project: &zed::Project,
+) -> zed::Result<Vec<(String, String)>> {
+    let settings =
+        zed::settings::ContextServerSettings::for_project(context_server_id.as_ref(), project)?;
+
+    let extension_settings: DatabaseExtensionSettings = settings
+        .settings
+        .map(zed::serde_json::from_value)
+        .transpose()
+        .unwrap_or_default()
+        .unwrap_or_default();
+
+    build_env(&extension_settings)
+}
+
+/// Sorted so the emitted env is deterministic regardless of `HashMap`
+/// iteration order (matters for testing, and for diffing what's spawned).
+/// Single-connection settings get a plain `DATABASE_URL`; multi-connection
+/// settings additionally need `active_connection` set to get one, on top of
+/// the `DATABASE_URL_<ALIAS>` every configured connection gets regardless.
+fn build_env(settings: &DatabaseExtensionSettings) -> zed::Result<Vec<(String, String)>> {
+    let mut env = Vec::new();
+    for alias in sorted_aliases(&settings.connections) {
+        let connection = &settings.connections[alias];
+        let Some(dsn) = literal_dsn(connection)? else {
+            continue;
+        };
+
+        env.push((format!("DATABASE_URL_{}", shout(alias)), dsn.clone()));
+        if is_active_connection(settings, alias) {
+            env.push(("DATABASE_URL".to_string(), dsn));
+        }
+    }
+
+    Ok(env)
+}
+
+/// The sorted aliases of a `connections` map, shared by every function that
+/// walks it, so the emitted env/JSON is deterministic regardless of
+/// `HashMap` iteration order (matters for testing, and for diffing what's
+/// spawned).
+fn sorted_aliases(connections: &HashMap<String, ConnectionSettings>) -> Vec<&String> {
+    let mut aliases: Vec<&String> = connections.keys().collect();
+    aliases.sort();
+    aliases
+}
+
+/// Whether `alias` is the one that should get the plain `DATABASE_URL` (on
+/// top of the `DATABASE_URL_<ALIAS>` every configured connection gets
+/// regardless): the explicit `active_connection`, or the only connection if
+/// there's just one.
+fn is_active_connection(settings: &DatabaseExtensionSettings, alias: &str) -> bool {
+    settings
+        .active_connection
+        .as_deref()
+        .map(|active| active == alias)
+        .unwrap_or(settings.connections.len() == 1)
+}
+
+/// Builds a DSN from literal settings fields, with no `Worktree` to expand
+/// `${VAR}` placeholders or read an `env_file` against (see
+/// [`resolve_connection_env`]). Rather than baking a literal `${VAR}` into
+/// `DATABASE_URL`, or silently producing no `DATABASE_URL` for an
+/// `env_file`-only connection, reject both loudly so a misconfigured
+/// connection fails instead of silently connecting to the wrong place (or
+/// not connecting at all).
+fn literal_dsn(connection: &ConnectionSettings) -> zed::Result<Option<String>> {
+    reject_unresolvable(connection)?;
+
+    if let Some(dsn) = &connection.dsn {
+        return Ok(Some(dsn.clone()));
+    }
+
+    let Some(host) = connection.host.as_deref() else {
+        return Ok(None);
+    };
+    let port = connection.port.unwrap_or(5432);
+    let user = connection.user.as_deref().unwrap_or_default();
+    let password = connection
+        .password
+        .as_deref()
+        .map(|password| format!(":{password}"))
+        .unwrap_or_default();
+    let database = connection.database.as_deref().unwrap_or_default();
+
+    Ok(Some(format!(
+        "postgres://{user}{password}@{host}:{port}/{database}"
+    )))
+}
+
+/// Rejects anything [`literal_dsn`] has no `Worktree` to resolve: an
+/// `env_file` reference (nothing here can read it — silently skipping it
+/// left a connection with no `DATABASE_URL` and no explanation), or a
+/// `${VAR}` placeholder in `dsn`/`host`/`user`/`password`/`database`
+/// (nothing here can expand it against a shell environment). Note that the
+/// spawned context server process still inherits Zed's own environment, so
+/// an already-exported `$PGPASSWORD` works without needing to be referenced
+/// here at all — only settings that *require* expansion at this layer are
+/// rejected.
+fn reject_unresolvable(connection: &ConnectionSettings) -> zed::Result<()> {
+    if connection.env_file.is_some() {
+        return Err(
+            "connection `env_file` can't be read when launching the context server (no \
+             worktree is reachable from `context_servers.database-operations-mcp.settings`); \
+             set `dsn`/`host` etc. directly instead"
+                .to_string(),
+        );
+    }
+
+    let fields = [
+        ("dsn", &connection.dsn),
+        ("host", &connection.host),
+        ("user", &connection.user),
+        ("password", &connection.password),
+        ("database", &connection.database),
+    ];
+    for (name, value) in fields {
+        if value.as_deref().is_some_and(|value| value.contains("${")) {
+            return Err(format!(
+                "connection `{name}` contains a `${{VAR}}` placeholder, which can't be \
+                 expanded when launching the context server (no worktree is reachable from \
+                 `context_servers.database-operations-mcp.settings`); set it to a literal \
+                 value, or export the variable in the environment Zed itself runs in so the \
+                 spawned process inherits it directly"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Builds the `connections` array `sqls` expects in its initialization
+/// options, from its own `lsp.sqls.settings` block.
+///
+/// The context server's settings aren't reachable from here:
+/// `language_server_initialization_options`/`_workspace_configuration` only
+/// ever receive a `Worktree`, and `ContextServerSettings` has no constructor
+/// that takes just a worktree (only `for_project`, see
+/// [`resolve_connection_env`]) — so `sqls` gets its own `connections` table
+/// under `lsp.sqls.settings` instead of sharing the context
+/// server's, read via `LspSettings::for_worktree`, which does support this.
+///
+/// `sqls` requires a `driver` per connection to pick a DB client; derived
+/// from the DSN's scheme (see [`sqls_driver`]) since a literal `dsn` setting
+/// isn't necessarily Postgres even though the host/port-constructed fallback
+/// in [`resolve_dsn`] always is.
+pub fn resolve_sqls_connections(worktree: &Worktree) -> zed::Result<Vec<zed::serde_json::Value>> {
+    let extension_settings = worktree_settings(worktree)?;
+    let shell_env: HashMap<String, String> = worktree.shell_env().into_iter().collect();
+
+    let mut connections = Vec::new();
+    for alias in sorted_aliases(&extension_settings.connections) {
+        let connection = &extension_settings.connections[alias];
+        if let Some(dsn) = resolve_dsn(worktree, &shell_env, connection)? {
+            connections.push(zed::serde_json::json!({
+                "alias": alias,
+                "driver": sqls_driver(&dsn),
+                "dataSourceName": dsn,
+            }));
+        }
+    }
+
+    Ok(connections)
+}
+
+/// Maps a DSN's scheme to the driver name `sqls` expects
+/// (`postgresql`/`mysql`/`sqlite3`). Defaults to `postgresql` for an
+/// unrecognized scheme, matching [`resolve_dsn`]'s host/port fallback, which
+/// only ever builds a `postgres://` DSN.
+fn sqls_driver(dsn: &str) -> &'static str {
+    match dsn.split("://").next().unwrap_or_default() {
+        "mysql" => "mysql",
+        "sqlite" | "sqlite3" | "file" => "sqlite3",
+        _ => "postgresql",
+    }
+}
+
+/// Builds the same `DATABASE_URL`-style environment as
+/// [`resolve_connection_env`], but resolved from the `Worktree` available to
+/// slash-command hooks rather than the `Project`-scoped context server
+/// settings those hooks can't reach — so, like [`resolve_sqls_connections`],
+/// it reads the `lsp.sqls.settings` connections block instead. This is what
+/// lets `/db-schema`, `/db-tables` and `/db-query` tell the introspection
+/// process which database to talk to.
+pub fn resolve_connection_env_from_worktree(worktree: &Worktree) -> zed::Result<Vec<(String, String)>> {
+    let extension_settings = worktree_settings(worktree)?;
+    let shell_env: HashMap<String, String> = worktree.shell_env().into_iter().collect();
+
+    let mut env = Vec::new();
+    for alias in sorted_aliases(&extension_settings.connections) {
+        let connection = &extension_settings.connections[alias];
+        let Some(dsn) = resolve_dsn(worktree, &shell_env, connection)? else {
+            continue;
+        };
+
+        env.push((format!("DATABASE_URL_{}", shout(alias)), dsn.clone()));
+        if is_active_connection(&extension_settings, alias) {
+            env.push(("DATABASE_URL".to_string(), dsn));
+        }
+    }
+
+    Ok(env)
+}
+
+/// Reads the `connections`/`active_connection` settings shared by
+/// [`resolve_sqls_connections`] and [`resolve_connection_env_from_worktree`]
+/// from `sqls`'s own `lsp.sqls.settings` block (see their doc comments for
+/// why the context server's own settings aren't reachable here).
+fn worktree_settings(worktree: &Worktree) -> zed::Result<DatabaseExtensionSettings> {
+    let lsp_settings = zed::settings::LspSettings::for_worktree(SQLS_LANGUAGE_SERVER_ID, worktree)?;
+    Ok(lsp_settings
+        .settings
+        .map(zed::serde_json::from_value)
+        .transpose()
+        .unwrap_or_default()
+        .unwrap_or_default())
+}
+
+fn resolve_dsn(
+    worktree: &Worktree,
+    shell_env: &HashMap<String, String>,
+    connection: &ConnectionSettings,
+) -> zed::Result<Option<String>> {
+    if let Some(dsn) = &connection.dsn {
+        return Ok(Some(resolve_placeholders(shell_env, dsn)?));
+    }
+
+    if let Some(env_file) = &connection.env_file {
+        if let Ok(contents) = worktree.read_text_file(env_file) {
+            if let Some(dsn) = parse_env_file(&contents).remove("DATABASE_URL") {
+                return Ok(Some(dsn));
+            }
+        }
+    }
+
+    let Some(host) = &connection.host else {
+        return Ok(None);
+    };
+    let port = connection.port.unwrap_or(5432);
+    let user = connection.user.as_deref().unwrap_or_default();
+    let password = connection
+        .password
+        .as_deref()
+        .map(|password| format!(":{password}"))
+        .unwrap_or_default();
+    let database = connection.database.as_deref().unwrap_or_default();
+
+    Ok(Some(resolve_placeholders(
+        shell_env,
+        &format!("postgres://{user}{password}@{host}:{port}/{database}"),
+    )?))
+}
+
+/// Expands `${VAR}` references against the user's shell environment, so a
+/// connection can read `password = "${PGPASSWORD}"` instead of committing it.
+///
+/// Takes the environment as a plain map (rather than a `Worktree`) so it's
+/// unit testable; [`resolve_dsn`] is the only real caller (for both
+/// [`resolve_sqls_connections`] and [`resolve_connection_env_from_worktree`])
+/// and supplies it from `worktree.shell_env()`.
+fn resolve_placeholders(shell_env: &HashMap<String, String>, value: &str) -> zed::Result<String> {
+    if !value.contains("${") {
+        return Ok(value.to_string());
+    }
+
+    let mut resolved = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            resolved.push_str(rest);
+            rest = "";
+            break;
+        };
+        resolved.push_str(&rest[..start]);
+        let var_name = &rest[start + 2..start + end];
+        let Some(value) = shell_env.get(var_name) else {
+            return Err(format!("`${{{var_name}}}` is not set in the shell environment"));
+        };
+        resolved.push_str(value);
+        rest = &rest[start + end + 1..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+fn parse_env_file(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (key, value) = line.split_once('=')?;
+            Some((
+                key.trim().to_string(),
+                value.trim().trim_matches('"').to_string(),
+            ))
+        })
+        .collect()
+}
+
+fn shout(alias: &str) -> String {
+    alias.to_uppercase().replace(['-', ' '], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(dsn: Option<&str>, host: Option<&str>) -> ConnectionSettings {
+        ConnectionSettings {
+            dsn: dsn.map(str::to_string),
+            host: host.map(str::to_string),
+            port: None,
+            user: Some("alice".to_string()),
+            password: None,
+            database: Some("app".to_string()),
+            env_file: None,
+        }
+    }
+
+    #[test]
+    fn literal_dsn_prefers_explicit_dsn_over_host_fields() {
+        let connection = connection(Some("postgres://explicit"), Some("ignored-host"));
+        assert_eq!(
+            literal_dsn(&connection).unwrap().as_deref(),
+            Some("postgres://explicit")
+        );
+    }
+
+    #[test]
+    fn literal_dsn_constructs_from_host_fields() {
+        let mut connection = connection(None, Some("db.internal"));
+        connection.port = Some(5433);
+        assert_eq!(
+            literal_dsn(&connection).unwrap().as_deref(),
+            Some("postgres://alice@db.internal:5433/app")
+        );
+    }
+
+    #[test]
+    fn literal_dsn_with_neither_dsn_nor_host_is_none() {
+        assert_eq!(literal_dsn(&connection(None, None)).unwrap(), None);
+    }
+
+    #[test]
+    fn literal_dsn_rejects_unexpanded_placeholder_in_dsn() {
+        let connection = connection(Some("postgres://u:${PGPASSWORD}@h/db"), None);
+        let err = literal_dsn(&connection).unwrap_err();
+        assert!(err.contains("${"), "error should mention the placeholder: {err}");
+    }
+
+    #[test]
+    fn literal_dsn_rejects_unexpanded_placeholder_in_host_fields() {
+        let mut connection = connection(None, Some("db.internal"));
+        connection.password = Some("${PGPASSWORD}".to_string());
+        let err = literal_dsn(&connection).unwrap_err();
+        assert!(err.contains("password"), "error should name the field: {err}");
+    }
+
+    #[test]
+    fn literal_dsn_rejects_env_file_instead_of_silently_dropping_it() {
+        let mut connection = connection(None, None);
+        connection.env_file = Some(".env".to_string());
+        let err = literal_dsn(&connection).unwrap_err();
+        assert!(err.contains("env_file"), "error should name the field: {err}");
+    }
+
+    #[test]
+    fn sqls_driver_maps_known_schemes() {
+        assert_eq!(sqls_driver("postgres://h/db"), "postgresql");
+        assert_eq!(sqls_driver("postgresql://h/db"), "postgresql");
+        assert_eq!(sqls_driver("mysql://h/db"), "mysql");
+        assert_eq!(sqls_driver("sqlite3:///path/to.db"), "sqlite3");
+        assert_eq!(sqls_driver("file:///path/to.db"), "sqlite3");
+    }
+
+    #[test]
+    fn sqls_driver_defaults_to_postgresql_for_unknown_scheme() {
+        assert_eq!(sqls_driver("oracle://h/db"), "postgresql");
+    }
+
+    #[test]
+    fn resolve_placeholders_expands_known_var() {
+        let mut shell_env = HashMap::new();
+        shell_env.insert("PGPASSWORD".to_string(), "secret".to_string());
+        let resolved = resolve_placeholders(&shell_env, "postgres://u:${PGPASSWORD}@h/db").unwrap();
+        assert_eq!(resolved, "postgres://u:secret@h/db");
+    }
+
+    #[test]
+    fn resolve_placeholders_errors_on_unknown_var() {
+        let err = resolve_placeholders(&HashMap::new(), "${MISSING}").unwrap_err();
+        assert!(err.contains("MISSING"));
+    }
+
+    #[test]
+    fn resolve_placeholders_passes_through_unmatched_brace() {
+        let resolved = resolve_placeholders(&HashMap::new(), "no closing ${brace").unwrap();
+        assert_eq!(resolved, "no closing ${brace");
+    }
+
+    #[test]
+    fn parse_env_file_skips_blanks_and_comments() {
+        let parsed = parse_env_file("\n# comment\nFOO=bar\n\nBAZ=\"quoted\"\n");
+        assert_eq!(parsed.get("FOO").map(String::as_str), Some("bar"));
+        assert_eq!(parsed.get("BAZ").map(String::as_str), Some("quoted"));
+        assert_eq!(parsed.len(), 2);
+    }
+
+    #[test]
+    fn build_env_picks_single_connection_as_active_by_default() {
+        let mut connections = HashMap::new();
+        connections.insert(
+            "primary".to_string(),
+            connection(Some("postgres://primary"), None),
+        );
+        let settings = DatabaseExtensionSettings {
+            connections,
+            active_connection: None,
+        };
+
+        assert_eq!(
+            build_env(&settings).unwrap(),
+            vec![
+                ("DATABASE_URL_PRIMARY".to_string(), "postgres://primary".to_string()),
+                ("DATABASE_URL".to_string(), "postgres://primary".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn build_env_uses_active_connection_when_multiple_are_configured() {
+        let mut connections = HashMap::new();
+        connections.insert(
+            "primary".to_string(),
+            connection(Some("postgres://primary"), None),
+        );
+        connections.insert(
+            "replica".to_string(),
+            connection(Some("postgres://replica"), None),
+        );
+        let settings = DatabaseExtensionSettings {
+            connections,
+            active_connection: Some("replica".to_string()),
+        };
+
+        // Deterministic (sorted by alias) regardless of HashMap iteration order.
+        assert_eq!(
+            build_env(&settings).unwrap(),
+            vec![
+                ("DATABASE_URL_PRIMARY".to_string(), "postgres://primary".to_string()),
+                ("DATABASE_URL_REPLICA".to_string(), "postgres://replica".to_string()),
+                ("DATABASE_URL".to_string(), "postgres://replica".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn build_env_with_multiple_connections_and_no_active_picks_none_as_default() {
+        let mut connections = HashMap::new();
+        connections.insert(
+            "primary".to_string(),
+            connection(Some("postgres://primary"), None),
+        );
+        connections.insert(
+            "replica".to_string(),
+            connection(Some("postgres://replica"), None),
+        );
+        let settings = DatabaseExtensionSettings {
+            connections,
+            active_connection: None,
+        };
+
+        assert_eq!(
+            build_env(&settings).unwrap(),
+            vec![
+                ("DATABASE_URL_PRIMARY".to_string(), "postgres://primary".to_string()),
+                ("DATABASE_URL_REPLICA".to_string(), "postgres://replica".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::fs;
+
+use zed_extension_api::{self as zed, serde_json, LanguageServerId, Worktree};
+
+use crate::connections;
+
+/// Name of the `[language_servers.*]` entry in `extension.toml`.
+pub const SERVER_ID: &str = "sqls";
+
+const GITHUB_REPO: &str = "sqls-server/sqls";
+
+/// Resolves the command used to launch the `sqls` SQL language server.
+///
+/// As in [`crate::settings::resolve_context_server_command`], an explicit
+/// override in the user's settings (here `lsp.sqls.binary`) comes first; then
+/// whatever `sqls` is already on the worktree's `PATH`, then a GitHub release
+/// downloaded into the extension's work directory.
+pub fn command(
+    language_server_id: &LanguageServerId,
+    worktree: &Worktree,
+) -> zed::Result<zed::Command> {
+    let lsp_settings = zed::settings::LspSettings::for_worktree(SERVER_ID, worktree)?;
+    if let Some(binary) = lsp_settings.binary {
+        if let Some(path) = binary.path {
+            return Ok(zed::Command {
+                command: path,
+                args: binary.arguments.unwrap_or_default(),
+                env: Default::default(),
+            });
+        }
+    }
+
+    if let Some(path) = worktree.which("sqls") {
+        return Ok(zed::Command {
+            command: path,
+            args: Vec::new(),
+            env: Default::default(),
+        });
+    }
+
+    let path = download_sqls(language_server_id)?;
+    Ok(zed::Command {
+        command: path,
+        args: Vec::new(),
+        env: Default::default(),
+    })
+}
+
+fn download_sqls(language_server_id: &LanguageServerId) -> zed::Result<String> {
+    zed::set_language_server_installation_status(
+        language_server_id,
+        &zed::LanguageServerInstallationStatus::CheckingForUpdate,
+    );
+
+    let release = zed::latest_github_release(
+        GITHUB_REPO,
+        zed::GithubReleaseOptions {
+            require_assets: true,
+            pre_release: false,
+        },
+    )?;
+
+    let (platform, arch) = zed::current_platform();
+    let asset_name = format!(
+        "sqls-{os}-{arch}.{ext}",
+        os = match platform {
+            zed::Os::Mac => "darwin",
+            zed::Os::Linux => "linux",
+            zed::Os::Windows => "windows",
+        },
+        arch = match arch {
+            zed::Architecture::Aarch64 => "arm64",
+            zed::Architecture::X8664 => "amd64",
+            zed::Architecture::X86 => "386",
+        },
+        ext = match platform {
+            zed::Os::Windows => "zip",
+            _ => "tar.gz",
+        },
+    );
+
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == asset_name)
+        .ok_or_else(|| format!("no sqls release asset found for {asset_name}"))?;
+
+    let version_dir = format!("sqls-{}", release.version);
+    let binary_name = match platform {
+        zed::Os::Windows => "sqls.exe",
+        _ => "sqls",
+    };
+    let binary_path = format!("{version_dir}/{binary_name}");
+
+    if !fs::metadata(&binary_path).is_ok_and(|stat| stat.is_file()) {
+        zed::set_language_server_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::Downloading,
+        );
+
+        zed::download_file(
+            &asset.download_url,
+            &version_dir,
+            match platform {
+                zed::Os::Windows => zed::DownloadedFileType::Zip,
+                _ => zed::DownloadedFileType::GzipTar,
+            },
+        )?;
+        zed::make_file_executable(&binary_path)?;
+    }
+
+    Ok(binary_path)
+}
+
+/// `sqls` takes its connection config as JSON initialization options (a
+/// `connections` list); feed it the connection(s) from its own
+/// `lsp.sqls.settings` block so completions reflect the real schema. The
+/// context server's `context_servers.database-operations-mcp.settings` block
+/// is not read here (see [`connections::resolve_sqls_connections`]).
+pub fn initialization_options(worktree: &Worktree) -> zed::Result<Option<serde_json::Value>> {
+    let connections = connections::resolve_sqls_connections(worktree)?;
+    if connections.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::json!({ "connections": connections })))
+}
+
+/// `sqls` also re-reads its config through `workspace/configuration`; return
+/// the same payload there so a live settings change takes effect without a
+/// server restart.
+pub fn workspace_configuration(worktree: &Worktree) -> zed::Result<Option<serde_json::Value>> {
+    initialization_options(worktree)
+}
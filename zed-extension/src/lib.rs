@@ -1,3 +1,9 @@
+mod connections;
+mod language_server;
+mod settings;
+mod slash_commands;
+
+use slash_commands::DatabaseSlashCommands;
 use zed_extension_api as zed;
 
 struct DatabaseOperationsMcpExtension;
@@ -15,19 +21,51 @@ impl zed::Extension for DatabaseOperationsMcpExtension {
 
     fn context_server_command(
         &mut self,
-        _id: &zed::ContextServerId,
-        _project: &zed::Project,
+        id: &zed::ContextServerId,
+        project: &zed::Project,
     ) -> zed::Result<zed::Command> {
-        Ok(zed::Command {
-            command: "uv".to_string(),
-            args: vec![
-                "run".to_string(),
-                "--project".to_string(),
-                ".".to_string(),
-                "--mcp".to_string(),
-            ],
-            env: Default::default(),
-        })
+        settings::resolve_context_server_command(id, project)
+    }
+
+    fn run_slash_command(
+        &self,
+        command: zed::SlashCommand,
+        args: Vec<String>,
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput, String> {
+        DatabaseSlashCommands::run(&command, &args, worktree)
+    }
+
+    fn complete_slash_command_argument(
+        &self,
+        command: zed::SlashCommand,
+        args: Vec<String>,
+    ) -> Result<Vec<zed::SlashCommandArgumentCompletion>, String> {
+        DatabaseSlashCommands::complete_argument(&command, &args, None)
+    }
+
+    fn language_server_command(
+        &mut self,
+        language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> zed::Result<zed::Command> {
+        language_server::command(language_server_id, worktree)
+    }
+
+    fn language_server_initialization_options(
+        &mut self,
+        _language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> zed::Result<Option<zed::serde_json::Value>> {
+        language_server::initialization_options(worktree)
+    }
+
+    fn language_server_workspace_configuration(
+        &mut self,
+        _language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> zed::Result<Option<zed::serde_json::Value>> {
+        language_server::workspace_configuration(worktree)
     }
 }
 
@@ -0,0 +1,105 @@
+use crate::connections;
+use zed_extension_api as zed;
+
+/// Resolves the command used to launch the MCP context server.
+///
+/// Precedence for the command itself:
+/// 1. An explicit `command`/`args`/`env` override from the user's
+///    `context_servers.database-operations-mcp.settings` block.
+/// 2. A `uv run --project . --mcp` invocation otherwise. `context_server_command`
+///    only ever receives a `Project`, which exposes `worktree_ids()` but no way
+///    to turn an id into a `Worktree`, so (unlike [`crate::language_server`],
+///    which does get a `Worktree`) there's no way to probe the worktree for a
+///    `pyproject.toml`/`uv.lock` here; `uv` is just assumed unless overridden.
+///
+/// Either way, the resolved connection environment (`DATABASE_URL` and
+/// friends, see [`connections::resolve_connection_env`]) is appended to
+/// `env`, so the server knows which database(s) to talk to.
+pub fn resolve_context_server_command(
+    context_server_id: &zed::ContextServerId,
+    project: &zed::Project,
+) -> zed::Result<zed::Command> {
+    let mut command = pick_command(user_configured_command(context_server_id, project)?);
+
+    command
+        .env
+        .extend(connections::resolve_connection_env(context_server_id, project)?);
+
+    Ok(command)
+}
+
+/// The override-vs-default precedence on its own, split out so it's unit
+/// testable without a live `Project`.
+fn pick_command(user_override: Option<zed::Command>) -> zed::Command {
+    user_override.unwrap_or_else(default_uv_command)
+}
+
+fn user_configured_command(
+    context_server_id: &zed::ContextServerId,
+    project: &zed::Project,
+) -> zed::Result<Option<zed::Command>> {
+    let settings =
+        zed::settings::ContextServerSettings::for_project(context_server_id.as_ref(), project)?;
+
+    let Some(command) = settings.command else {
+        return Ok(None);
+    };
+
+    let path = command
+        .path
+        .ok_or_else(|| "context server command override is missing a `path`".to_string())?;
+
+    Ok(Some(zed::Command {
+        command: path,
+        args: command.arguments.unwrap_or_default(),
+        env: command.env.unwrap_or_default().into_iter().collect(),
+    }))
+}
+
+pub(crate) fn default_uv_command() -> zed::Command {
+    zed::Command {
+        command: "uv".to_string(),
+        args: vec![
+            "run".to_string(),
+            "--project".to_string(),
+            ".".to_string(),
+            "--mcp".to_string(),
+        ],
+        env: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pick_command_uses_override_when_present() {
+        let user_override = zed::Command {
+            command: "node".to_string(),
+            args: vec!["server.js".to_string()],
+            env: Default::default(),
+        };
+
+        let command = pick_command(Some(user_override));
+
+        assert_eq!(command.command, "node");
+        assert_eq!(command.args, vec!["server.js".to_string()]);
+    }
+
+    #[test]
+    fn pick_command_falls_back_to_default_uv_invocation() {
+        let command = pick_command(None);
+
+        assert_eq!(command.command, "uv");
+        assert_eq!(
+            command.args,
+            vec![
+                "run".to_string(),
+                "--project".to_string(),
+                ".".to_string(),
+                "--mcp".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use zed_extension_api::{self as zed, Worktree};
+
+use crate::{connections, settings};
+
+/// Names of the slash commands registered in `extension.toml`.
+pub const DB_SCHEMA: &str = "db-schema";
+pub const DB_TABLES: &str = "db-tables";
+pub const DB_QUERY: &str = "db-query";
+
+/// Handles `run_slash_command` / `complete_slash_command_argument` for the
+/// `/db-schema`, `/db-tables` and `/db-query` commands.
+///
+/// Completions and command output are both fetched by issuing a lightweight
+/// introspection request through the same MCP backend the context server
+/// speaks to, rather than opening a second connection to the database.
+pub struct DatabaseSlashCommands;
+
+impl DatabaseSlashCommands {
+    pub fn run(
+        command: &zed::SlashCommand,
+        args: &[String],
+        worktree: Option<&Worktree>,
+    ) -> Result<zed::SlashCommandOutput, String> {
+        match command.name.as_str() {
+            DB_SCHEMA => {
+                let table = args
+                    .first()
+                    .ok_or_else(|| "usage: /db-schema <table>".to_string())?;
+                let schema = introspect(worktree, "describe_table", table)?;
+                Ok(labeled_output(format!("Schema for `{table}`"), schema))
+            }
+            DB_TABLES => {
+                let tables = introspect(worktree, "list_tables", "")?;
+                Ok(labeled_output("Database tables".to_string(), tables))
+            }
+            DB_QUERY => {
+                let sql = args.join(" ");
+                if sql.trim().is_empty() {
+                    return Err("usage: /db-query <sql>".to_string());
+                }
+                let result = introspect(worktree, "run_query", &sql)?;
+                Ok(labeled_output(format!("Result of `{sql}`"), result))
+            }
+            other => Err(format!("unknown slash command: {other}")),
+        }
+    }
+
+    pub fn complete_argument(
+        command: &zed::SlashCommand,
+        args: &[String],
+        worktree: Option<&Worktree>,
+    ) -> Result<Vec<zed::SlashCommandArgumentCompletion>, String> {
+        match command.name.as_str() {
+            DB_SCHEMA => {
+                let prefix = args.first().map(String::as_str).unwrap_or("");
+                let tables = introspect(worktree, "list_tables", "")?;
+                Ok(tables
+                    .lines()
+                    .filter(|table| table.starts_with(prefix))
+                    .map(|table| zed::SlashCommandArgumentCompletion {
+                        label: table.to_string(),
+                        new_text: table.to_string(),
+                        run_command: true,
+                    })
+                    .collect())
+            }
+            DB_TABLES | DB_QUERY => Ok(Vec::new()),
+            other => Err(format!("unknown slash command: {other}")),
+        }
+    }
+}
+
+/// Issues a one-shot introspection request against the context server
+/// backend and returns its raw text output.
+///
+/// Reuses [`settings::default_uv_command`] rather than duplicating its
+/// `uv run --project . --mcp` invocation as a literal. There's no
+/// `ContextServerSettings` constructor that takes just a worktree (see
+/// `crate::connections`), so a user-configured command override isn't
+/// visible here either — this always shells out to the default `uv`
+/// invocation, the same limitation `sqls`' settings lookup works around by
+/// reading its own `lsp.sqls.settings` block instead. The connection env
+/// (`DATABASE_URL` and friends) is resolved the same way, via
+/// [`connections::resolve_connection_env_from_worktree`], so the spawned
+/// process knows which database to introspect. That env is only added when a
+/// worktree is given; argument completion always passes `None`, so its
+/// requests run without it.
+fn introspect(worktree: Option<&Worktree>, action: &str, argument: &str) -> Result<String, String> {
+    let command = settings::default_uv_command();
+    let mut args = command.args;
+    args.push("--introspect".to_string());
+    args.push(action.to_string());
+    if !argument.is_empty() {
+        args.push(argument.to_string());
+    }
+
+    let mut env = command.env;
+    if let Some(worktree) = worktree {
+        env.extend(connections::resolve_connection_env_from_worktree(worktree)?);
+    }
+
+    let output = zed::process::Command::new(&command.command)
+        .args(args)
+        .envs(env)
+        .output()
+        .map_err(|err| err.to_string())?;
+
+    if output.status != Some(0) {
+        return Err(String::from_utf8_lossy(&output.stderr).into_owned());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn labeled_output(label: String, text: String) -> zed::SlashCommandOutput {
+    zed::SlashCommandOutput {
+        sections: vec![zed::SlashCommandOutputSection {
+            range: (0..text.len() as u32).into(),
+            label,
+        }],
+        text,
+    }
+}